@@ -0,0 +1,141 @@
+//! Scoped, expiring authentication tokens.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use data_encoding::HEXLOWER;
+use rand::Rng as _;
+use tokio::sync::RwLock;
+
+/// Capability granted to a token.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Scope {
+    /// Read-only access to project browsing endpoints.
+    ReadOnly,
+    /// Key-bearing write operations, e.g. unsealing or creating a key.
+    Full,
+}
+
+/// Default lifetime granted to a newly minted token.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Metadata stored alongside an issued token.
+struct Grant {
+    /// Scopes the token is allowed to act within.
+    scopes: HashSet<Scope>,
+    /// Instant after which the token is no longer valid.
+    expires_at: Instant,
+}
+
+impl Grant {
+    /// Returns `true` if the grant covers `scope` and hasn't expired yet.
+    fn permits(&self, scope: Scope) -> bool {
+        Instant::now() < self.expires_at && self.scopes.contains(&scope)
+    }
+}
+
+/// Tracks issued authentication tokens and the capabilities/expiry attached to each.
+#[derive(Clone, Default)]
+pub struct TokenStore {
+    /// Issued tokens, keyed by their hex-encoded value.
+    grants: Arc<RwLock<HashMap<String, Grant>>>,
+}
+
+impl TokenStore {
+    /// Create an empty [`TokenStore`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mint a new token granting `scopes` for `ttl`, pruning expired tokens in the process.
+    pub async fn mint(&self, scopes: HashSet<Scope>, ttl: Duration) -> String {
+        let token_data = rand::thread_rng().gen::<[u8; 32]>();
+        let token = HEXLOWER.encode(&token_data);
+
+        let mut grants = self.grants.write().await;
+        prune(&mut grants);
+        grants.insert(
+            token.clone(),
+            Grant {
+                scopes,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        token
+    }
+
+    /// Mint a token with every [`Scope`], valid for [`DEFAULT_TTL`].
+    pub async fn mint_full(&self) -> String {
+        self.mint(
+            [Scope::ReadOnly, Scope::Full].iter().copied().collect(),
+            DEFAULT_TTL,
+        )
+        .await
+    }
+
+    /// Returns `true` if `token` is a currently valid token that grants `scope`.
+    pub async fn check(&self, token: Option<&str>, scope: Scope) -> bool {
+        let mut grants = self.grants.write().await;
+        prune(&mut grants);
+        token
+            .and_then(|token| grants.get(token))
+            .is_some_and(|grant| grant.permits(scope))
+    }
+}
+
+/// Remove all grants whose expiry has passed.
+fn prune(grants: &mut HashMap<String, Grant>) {
+    let now = Instant::now();
+    grants.retain(|_, grant| grant.expires_at > now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn enforces_scope() {
+        let store = TokenStore::new();
+        let token = store
+            .mint([Scope::ReadOnly].iter().copied().collect(), DEFAULT_TTL)
+            .await;
+
+        assert!(store.check(Some(&token), Scope::ReadOnly).await);
+        assert!(!store.check(Some(&token), Scope::Full).await);
+        assert!(!store.check(None, Scope::ReadOnly).await);
+        assert!(!store.check(Some("bogus"), Scope::ReadOnly).await);
+    }
+
+    #[tokio::test]
+    async fn enforces_expiry() {
+        let store = TokenStore::new();
+        let token = store
+            .mint(
+                [Scope::Full].iter().copied().collect(),
+                Duration::from_millis(1),
+            )
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert!(!store.check(Some(&token), Scope::Full).await);
+    }
+
+    #[tokio::test]
+    async fn prunes_expired_grants_on_mint() {
+        let store = TokenStore::new();
+        let expired = store
+            .mint(
+                [Scope::Full].iter().copied().collect(),
+                Duration::from_millis(1),
+            )
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        store.mint_full().await;
+
+        assert_eq!(store.grants.read().await.contains_key(&expired), false);
+    }
+}
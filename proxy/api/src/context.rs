@@ -2,16 +2,18 @@
 
 use std::sync::Arc;
 
-use data_encoding::HEXLOWER;
-use rand::Rng as _;
-use tokio::sync::RwLock;
-
 use coco::PeerControl;
 
+use crate::auth_token::{Scope, TokenStore};
+use crate::keystore_registry::{KeystoreRegistry, DEFAULT_KEY_NAME};
 use crate::service;
+use crate::session_storage::SessionStore;
+use crate::shamir;
 
 #[cfg(test)]
 use coco::{signer, RunConfig};
+#[cfg(test)]
+use crate::session_storage::MemoryStore;
 
 /// Container to pass down dependencies into HTTP filter chains.
 #[derive(Clone)]
@@ -32,19 +34,19 @@ impl Context {
         }
     }
 
-    /// Returns the [`kv::Store`] for persistent storage.
-    pub const fn store(&self) -> &kv::Store {
+    /// Returns the [`SessionStore`] for persistent storage.
+    pub fn store(&self) -> &(dyn SessionStore + Send + Sync) {
         match self {
-            Self::Sealed(sealed) => &sealed.store,
-            Self::Unsealed(unsealed) => &unsealed.store,
+            Self::Sealed(sealed) => &*sealed.store,
+            Self::Unsealed(unsealed) => &*unsealed.store,
         }
     }
 
-    /// Returns a mutable reference to the authentication cookie value.
-    pub fn auth_token(&self) -> Arc<RwLock<Option<String>>> {
+    /// Returns the [`TokenStore`] tracking issued authentication tokens.
+    pub fn tokens(&self) -> Arc<TokenStore> {
         match self {
-            Self::Sealed(sealed) => sealed.auth_token.clone(),
-            Self::Unsealed(unsealed) => unsealed.auth_token.clone(),
+            Self::Sealed(sealed) => sealed.tokens.clone(),
+            Self::Unsealed(unsealed) => unsealed.tokens.clone(),
         }
     }
 
@@ -68,13 +70,8 @@ impl Context {
         &mut self,
         passphrase: coco::keystore::SecUtf8,
     ) -> Result<String, crate::error::Error> {
-        let keystore = self.keystore();
-        let key = tokio::task::spawn_blocking(move || keystore.get(passphrase))
+        self.unseal_named(DEFAULT_KEY_NAME.to_string(), passphrase)
             .await
-            .expect("Task to unseal key was aborted")?;
-        self.service_handle().set_secret_key(key);
-        let auth_token = self.reset_auth_token().await;
-        Ok(auth_token)
     }
 
     /// Create a key and store it encrypted with the given passphrase. Then restart the coco
@@ -87,35 +84,120 @@ impl Context {
         &mut self,
         passphrase: coco::keystore::SecUtf8,
     ) -> Result<String, crate::error::Error> {
-        let keystore = self.keystore();
+        self.create_key_named(DEFAULT_KEY_NAME.to_string(), passphrase)
+            .await
+    }
+
+    /// Create a key named `name` and store it encrypted with the given passphrase, then restart
+    /// the coco service to use it. Returns the auth token required to access the keystore.
+    ///
+    /// # Errors
+    ///
+    /// Errors when the storage backend fails to persist the key or a key already exists under
+    /// `name`.
+    pub async fn create_key_named(
+        &mut self,
+        name: String,
+        passphrase: coco::keystore::SecUtf8,
+    ) -> Result<String, crate::error::Error> {
+        let _lock = self.keystores().lock().await?;
+        let keystore = self.keystores().get_or_open(&name).await;
         let key = tokio::task::spawn_blocking(move || keystore.create_key(passphrase))
             .await
             .expect("Task to create key was aborted")?;
         self.service_handle().set_secret_key(key);
-        let auth_token = self.reset_auth_token().await;
+        let auth_token = self.tokens().mint_full().await;
         Ok(auth_token)
     }
 
-    fn keystore(&self) -> Arc<dyn coco::keystore::Keystore + Sync + Send> {
-        match self {
-            Self::Sealed(sealed) => sealed.keystore.clone(),
-            Self::Unsealed(unsealed) => unsealed.keystore.clone(),
-        }
+    /// Unseal the key named `name` and restart the coco service with it. Returns the auth token
+    /// required to access the keystore.
+    ///
+    /// # Errors
+    ///
+    /// * Errors if the passphrase is wrong.
+    /// * Errors if backend fails to retrieve the data.
+    /// * Errors if there is no key named `name` in the storage yet.
+    pub async fn unseal_named(
+        &mut self,
+        name: String,
+        passphrase: coco::keystore::SecUtf8,
+    ) -> Result<String, crate::error::Error> {
+        let _lock = self.keystores().lock().await?;
+        let keystore = self.keystores().get_or_open(&name).await;
+        let key = tokio::task::spawn_blocking(move || keystore.get(passphrase))
+            .await
+            .expect("Task to unseal key was aborted")?;
+        self.service_handle().set_secret_key(key);
+        let auth_token = self.tokens().mint_full().await;
+        Ok(auth_token)
+    }
+
+    /// Names of the keys held in the keystore, opened so far this session.
+    pub async fn list_keys(&self) -> Vec<String> {
+        self.keystores().names().await
+    }
+
+    /// Split the secret key protected by `passphrase` into `n` Shamir shares, any `k` of which
+    /// can later reconstruct it via [`Context::recover_from_shares`]. Does not change which key
+    /// the running service uses.
+    ///
+    /// # Errors
+    ///
+    /// * Errors if the passphrase is wrong or the backend fails to retrieve the key.
+    /// * Errors if `k` and `n` do not describe a valid threshold scheme.
+    pub async fn split_key_shares(
+        &self,
+        passphrase: coco::keystore::SecUtf8,
+        k: u8,
+        n: u8,
+    ) -> Result<Vec<shamir::Share>, crate::error::Error> {
+        let keystore = self.keystores().get_or_open(DEFAULT_KEY_NAME).await;
+        let key = tokio::task::spawn_blocking(move || keystore.get(passphrase))
+            .await
+            .expect("Task to unseal key was aborted")?;
+        Ok(shamir::split(key.as_ref(), k, n)?)
+    }
+
+    /// Reconstruct a secret key from at least `k` of the shares produced by
+    /// [`Context::split_key_shares`] and restart the coco service to use it. Returns the auth
+    /// token required to access the keystore.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the shares are insufficient, inconsistent, or do not decode to a valid key.
+    pub async fn recover_from_shares(
+        &mut self,
+        shares: Vec<shamir::Share>,
+    ) -> Result<String, crate::error::Error> {
+        let _lock = self.keystores().lock().await?;
+        let bytes = shamir::recover(&shares)?;
+        let key = coco::keys::SecretKey::try_from(bytes.as_slice())
+            .map_err(|_| crate::error::Error::InvalidRecoveredKey)?;
+        self.service_handle().set_secret_key(key);
+        let auth_token = self.tokens().mint_full().await;
+        Ok(auth_token)
+    }
+
+    /// Mint a token restricted to `scopes`, valid for `ttl`.
+    pub async fn mint_scoped_token(
+        &self,
+        scopes: std::collections::HashSet<Scope>,
+        ttl: std::time::Duration,
+    ) -> String {
+        self.tokens().mint(scopes, ttl).await
     }
 
-    /// Generate a new authentication token and store it.
-    async fn reset_auth_token(&self) -> String {
-        let new_token_data = rand::thread_rng().gen::<[u8; 32]>();
-        let new_token = HEXLOWER.encode(&new_token_data);
-        let auth_token_lock = self.auth_token();
-        let mut auth_token = auth_token_lock.write().await;
-        *auth_token = Some(new_token.clone());
-        new_token
+    fn keystores(&self) -> &KeystoreRegistry {
+        match self {
+            Self::Sealed(sealed) => &sealed.keystores,
+            Self::Unsealed(unsealed) => &unsealed.keystores,
+        }
     }
 
-    /// Returns `true` if `token` matches the stored authentication token.
-    pub async fn check_auth_token(&self, token: Option<String>) -> bool {
-        token == *self.auth_token().read().await
+    /// Returns `true` if `token` is currently valid and grants `scope`.
+    pub async fn check_auth_token(&self, token: Option<String>, scope: Scope) -> bool {
+        self.tokens().check(token.as_deref(), scope).await
     }
 }
 
@@ -138,31 +220,31 @@ pub struct Unsealed {
     pub peer_control: PeerControl,
     /// [`coco::State`] to operate on the local monorepo.
     pub state: coco::State,
-    /// [`kv::Store`] used for session state and cache.
-    pub store: kv::Store,
+    /// [`SessionStore`] used for session state and cache.
+    pub store: Arc<dyn SessionStore + Send + Sync>,
     /// Flag to control if the stack is set up in test mode.
     pub test: bool,
     /// Handle to control the service configuration.
     pub service_handle: service::Handle,
-    /// Cookie set on unsealing the key store.
-    pub auth_token: Arc<RwLock<Option<String>>>,
-    /// Reference to the key store.
-    pub keystore: Arc<dyn coco::keystore::Keystore + Send + Sync>,
+    /// Issued authentication tokens, scoped and expiring.
+    pub tokens: Arc<TokenStore>,
+    /// Registry of named keys held in the key store.
+    pub keystores: KeystoreRegistry,
 }
 
 /// Context for HTTP request if the coco peer APIs have not been initialized yet.
 #[derive(Clone)]
 pub struct Sealed {
-    /// [`kv::Store`] used for session state and cache.
-    pub store: kv::Store,
+    /// [`SessionStore`] used for session state and cache.
+    pub store: Arc<dyn SessionStore + Send + Sync>,
     /// Flag to control if the stack is set up in test mode.
     pub test: bool,
     /// Handle to control the service configuration.
     pub service_handle: service::Handle,
-    /// Cookie set on unsealing the key store.
-    pub auth_token: Arc<RwLock<Option<String>>>,
-    /// Reference to the key store.
-    pub keystore: Arc<dyn coco::keystore::Keystore + Send + Sync>,
+    /// Issued authentication tokens, scoped and expiring.
+    pub tokens: Arc<TokenStore>,
+    /// Registry of named keys held in the key store.
+    pub keystores: KeystoreRegistry,
 }
 
 impl Unsealed {
@@ -175,7 +257,8 @@ impl Unsealed {
     /// * creation of the [`kv::Store`] fails
     #[cfg(test)]
     pub async fn tmp(tmp_dir: &tempfile::TempDir) -> Result<Self, crate::error::Error> {
-        let store = kv::Store::new(kv::Config::new(tmp_dir.path().join("store")))?;
+        let kv_store = kv::Store::new(kv::Config::new(tmp_dir.path().join("store")))?;
+        let store: Arc<dyn SessionStore + Send + Sync> = Arc::new(MemoryStore::new());
 
         let key = coco::keys::SecretKey::new();
         let signer = signer::BoxedSigner::from(signer::SomeSigner { signer: key });
@@ -183,7 +266,7 @@ impl Unsealed {
         let (peer_control, state) = {
             let config = coco::config::default(key, tmp_dir.path())?;
             let (peer, state) =
-                coco::into_peer_state(config, signer.clone(), store.clone(), RunConfig::default())
+                coco::into_peer_state(config, signer.clone(), kv_store, RunConfig::default())
                     .await?;
 
             let peer_control = peer.control();
@@ -192,14 +275,72 @@ impl Unsealed {
             (peer_control, state)
         };
 
+        let keystores = KeystoreRegistry::new(tmp_dir.path().join("keystores"));
+        keystores
+            .insert(DEFAULT_KEY_NAME, Arc::new(coco::keystore::memory()))
+            .await;
+
         Ok(Self {
             peer_control,
             state,
             store,
             test: false,
             service_handle: service::Handle::dummy(),
-            auth_token: Arc::new(RwLock::new(None)),
-            keystore: Arc::new(coco::keystore::memory()),
+            tokens: Arc::new(TokenStore::new()),
+            keystores,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unsealing_a_named_key_requires_that_keys_own_passphrase() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut context = Context::from(Unsealed::tmp(&tmp_dir).await.unwrap());
+
+        let laptop_passphrase = coco::keystore::SecUtf8::from("laptop-passphrase".to_string());
+        let phone_passphrase = coco::keystore::SecUtf8::from("phone-passphrase".to_string());
+
+        context
+            .create_key_named("laptop".to_string(), laptop_passphrase.clone())
+            .await
+            .unwrap();
+        context
+            .create_key_named("phone".to_string(), phone_passphrase.clone())
+            .await
+            .unwrap();
+
+        // Each name's key is only reachable with its own passphrase, never the other's.
+        assert!(context
+            .unseal_named("laptop".to_string(), phone_passphrase.clone())
+            .await
+            .is_err());
+        assert!(context
+            .unseal_named("phone".to_string(), laptop_passphrase.clone())
+            .await
+            .is_err());
+
+        assert!(context
+            .unseal_named("laptop".to_string(), laptop_passphrase)
+            .await
+            .is_ok());
+        assert!(context
+            .unseal_named("phone".to_string(), phone_passphrase)
+            .await
+            .is_ok());
+
+        let mut names = context.list_keys().await;
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                DEFAULT_KEY_NAME.to_string(),
+                "laptop".to_string(),
+                "phone".to_string()
+            ]
+        );
+    }
+}
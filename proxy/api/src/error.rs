@@ -0,0 +1,20 @@
+//! Error types returned from the [`Context`](crate::context::Context) API.
+
+use thiserror::Error;
+
+/// Errors surfaced by the HTTP API.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Failed to split or recover a Shamir-shared secret key.
+    #[error(transparent)]
+    Shamir(#[from] crate::shamir::Error),
+    /// Bytes recovered from Shamir shares did not decode to a valid secret key.
+    #[error("recovered bytes do not decode to a valid secret key")]
+    InvalidRecoveredKey,
+    /// The keystore's cross-process advisory lock could not be acquired before its timeout.
+    #[error("keystore is locked by another in-flight create/unseal/recover operation")]
+    KeystoreLocked,
+    /// I/O failure manipulating the keystore lock file.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
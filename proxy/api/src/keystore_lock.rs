@@ -0,0 +1,180 @@
+//! Cross-process advisory lock guarding mutating keystore operations.
+
+use std::fs::{self, File};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rand::Rng as _;
+
+/// Default for how long [`KeystoreLock::acquire`] waits for the lock before giving up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a lock file may sit unreleased before it's considered abandoned by a crashed process
+/// and removed.
+pub const STALE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Delay before the first acquisition retry; doubled after each further failed attempt, up to
+/// [`MAX_RETRY_DELAY`].
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Upper bound on the delay between acquisition retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Held advisory lock over a store directory's keystore. Releases the lock file on drop.
+pub struct KeystoreLock {
+    /// Path of the held lock file.
+    path: PathBuf,
+    /// Random token written into the lock file, identifying this particular holder so `drop`
+    /// only ever removes a lock file this instance itself created.
+    token: u64,
+}
+
+impl KeystoreLock {
+    /// Acquire the lock file `<dir>/.keystore.lock`, retrying with backoff until `timeout`
+    /// elapses.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`crate::error::Error::KeystoreLocked`] if the lock file still exists after
+    /// `timeout` (including a stale one that could not be removed), or with the underlying I/O
+    /// error if the lock file can't be created/inspected for a reason other than already
+    /// existing.
+    pub async fn acquire(dir: &Path, timeout: Duration) -> Result<Self, crate::error::Error> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(".keystore.lock");
+        let deadline = Instant::now() + timeout;
+        let mut delay = RETRY_DELAY;
+
+        loop {
+            let token = rand::thread_rng().gen::<u64>();
+            match File::options()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| write!(file, "{token}"))
+            {
+                Ok(()) => return Ok(Self { path, token }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        // Racing with whoever is about to steal the same stale lock is fine: at
+                        // most one of us wins the following `create_new`, and each holder's
+                        // `drop` only ever removes a file carrying its own token, so a losing
+                        // attempt here can never delete the winner's freshly created lock.
+                        let _ = fs::remove_file(&path);
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(crate::error::Error::KeystoreLocked);
+                    }
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for KeystoreLock {
+    fn drop(&mut self) {
+        // Only remove the lock file if it still carries our token: if our hold outlasted
+        // `STALE_TIMEOUT`, another process may have already declared it abandoned, deleted it and
+        // created its own, in which case removing it here would drop someone else's active lock.
+        if fs::read_to_string(&self.path).ok().as_deref() == Some(self.token.to_string().as_str())
+        {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns `true` if the lock file at `path` was last modified longer ago than [`STALE_TIMEOUT`].
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| modified.elapsed().unwrap_or_default() > STALE_TIMEOUT)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn second_acquire_times_out_while_first_is_held() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let _held = KeystoreLock::acquire(tmp_dir.path(), DEFAULT_TIMEOUT)
+            .await
+            .unwrap();
+
+        let result = KeystoreLock::acquire(tmp_dir.path(), Duration::from_millis(100)).await;
+
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::KeystoreLocked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn lock_is_released_on_drop() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        {
+            let _held = KeystoreLock::acquire(tmp_dir.path(), DEFAULT_TIMEOUT)
+                .await
+                .unwrap();
+        }
+
+        assert!(KeystoreLock::acquire(tmp_dir.path(), DEFAULT_TIMEOUT)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn times_out_even_when_a_stale_lock_cannot_be_removed() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        // A directory at the lock path can never be removed by `fs::remove_file`, standing in for
+        // a stale lock file that's stuck for some other reason (e.g. a permissions issue); the
+        // stale-steal branch must still respect `timeout` rather than retrying forever.
+        let lock_path = tmp_dir.path().join(".keystore.lock");
+        fs::create_dir(&lock_path).unwrap();
+        let stale_time = std::time::SystemTime::now() - STALE_TIMEOUT - Duration::from_secs(1);
+        File::open(&lock_path).unwrap().set_modified(stale_time).unwrap();
+
+        let result = KeystoreLock::acquire(tmp_dir.path(), Duration::from_millis(200)).await;
+
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::KeystoreLocked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn concurrent_lock_holders_serialize() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path().to_path_buf();
+        // Tracks how many tasks are inside the locked section at once; any overlap bumps it
+        // above 1, which `max_concurrent` below would then catch.
+        let concurrent = Arc::new(AtomicUsize::new(0));
+        let max_concurrent = Arc::new(AtomicUsize::new(0));
+
+        let run = |dir: PathBuf, concurrent: Arc<AtomicUsize>, max_concurrent: Arc<AtomicUsize>| {
+            tokio::spawn(async move {
+                let _lock = KeystoreLock::acquire(&dir, DEFAULT_TIMEOUT).await.unwrap();
+                let now = concurrent.fetch_add(1, Ordering::SeqCst) + 1;
+                max_concurrent.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                concurrent.fetch_sub(1, Ordering::SeqCst);
+            })
+        };
+
+        let first = run(dir.clone(), concurrent.clone(), max_concurrent.clone());
+        let second = run(dir.clone(), concurrent.clone(), max_concurrent.clone());
+
+        first.await.unwrap();
+        second.await.unwrap();
+
+        assert_eq!(max_concurrent.load(Ordering::SeqCst), 1);
+    }
+}
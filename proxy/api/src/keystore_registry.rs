@@ -0,0 +1,93 @@
+//! Multiple named keys addressed by a logical name, e.g. distinct device/identity keys sharing
+//! one store directory.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use coco::keystore::Keystore;
+
+use crate::keystore_lock::{self, KeystoreLock};
+
+/// Name used by [`crate::context::Context::unseal_keystore`]/
+/// [`crate::context::Context::create_key`] for backwards compatibility with single-key setups.
+pub const DEFAULT_KEY_NAME: &str = "default";
+
+/// Registry of named keystores, each holding a single key addressed by `name`.
+#[derive(Clone)]
+pub struct KeystoreRegistry {
+    /// Directory new named keystores are created under.
+    dir: PathBuf,
+    /// Keystores opened so far, keyed by name.
+    opened: Arc<RwLock<HashMap<String, Arc<dyn Keystore + Send + Sync>>>>,
+}
+
+impl KeystoreRegistry {
+    /// Create a registry that opens/creates named keystores under `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            opened: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Register an already-constructed keystore under `name`, e.g. the in-memory keystore used in
+    /// tests.
+    pub async fn insert(&self, name: impl Into<String>, keystore: Arc<dyn Keystore + Send + Sync>) {
+        self.opened.write().await.insert(name.into(), keystore);
+    }
+
+    /// Return the keystore for `name`, opening (or creating) it on disk if it hasn't been
+    /// accessed yet this session.
+    pub async fn get_or_open(&self, name: &str) -> Arc<dyn Keystore + Send + Sync> {
+        if let Some(keystore) = self.opened.read().await.get(name) {
+            return keystore.clone();
+        }
+        let keystore: Arc<dyn Keystore + Send + Sync> =
+            Arc::new(coco::keystore::file(self.dir.join(name)));
+        self.opened
+            .write()
+            .await
+            .insert(name.to_string(), keystore.clone());
+        keystore
+    }
+
+    /// Names of every keystore opened so far in this session.
+    pub async fn names(&self) -> Vec<String> {
+        self.opened.read().await.keys().cloned().collect()
+    }
+
+    /// Acquire the cross-process lock guarding mutating operations (create/unseal/recover)
+    /// against this registry's store directory.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the lock is still held by another in-flight operation after
+    /// [`keystore_lock::DEFAULT_TIMEOUT`].
+    pub async fn lock(&self) -> Result<KeystoreLock, crate::error::Error> {
+        KeystoreLock::acquire(&self.dir, keystore_lock::DEFAULT_TIMEOUT).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tracks_multiple_named_keystores() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let registry = KeystoreRegistry::new(tmp_dir.path());
+        registry
+            .insert("laptop", Arc::new(coco::keystore::memory()))
+            .await;
+        registry
+            .insert("phone", Arc::new(coco::keystore::memory()))
+            .await;
+
+        let mut names = registry.names().await;
+        names.sort();
+        assert_eq!(names, vec!["laptop".to_string(), "phone".to_string()]);
+    }
+}
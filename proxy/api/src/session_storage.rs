@@ -0,0 +1,145 @@
+//! Abstraction over the storage backend used for session state and cache entries.
+
+use crate::error;
+
+/// Key-value operations required from a storage backend for session state and cache entries.
+pub trait SessionStore {
+    /// Fetch the raw bytes stored under `key`, if any.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, error::Error>;
+
+    /// Store `value` under `key`, overwriting any previous value.
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), error::Error>;
+
+    /// Return all key/value pairs whose key starts with `prefix`.
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, error::Error>;
+}
+
+/// [`SessionStore`] backed by an on-disk [`kv::Store`].
+pub struct DiskStore {
+    /// Underlying on-disk key-value store.
+    store: kv::Store,
+}
+
+impl DiskStore {
+    /// Open (or create) a [`DiskStore`] at the given [`kv::Config`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if the underlying [`kv::Store`] fails to open.
+    pub fn new(config: kv::Config) -> Result<Self, error::Error> {
+        Ok(Self {
+            store: kv::Store::new(config)?,
+        })
+    }
+
+    /// The bucket all session state and cache entries are kept in.
+    fn bucket(&self) -> Result<kv::Bucket<'_, String, kv::Raw>, error::Error> {
+        Ok(self.store.bucket(Some("session"))?)
+    }
+}
+
+impl SessionStore for DiskStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, error::Error> {
+        Ok(self
+            .bucket()?
+            .get(&key.to_string())?
+            .map(|raw| raw.to_vec()))
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), error::Error> {
+        self.bucket()?
+            .set(&key.to_string(), &kv::Raw::from(value))?;
+        Ok(())
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, error::Error> {
+        let mut entries = Vec::new();
+        for item in self.bucket()?.iter() {
+            let item = item?;
+            let key: String = item.key()?;
+            if key.starts_with(prefix) {
+                let value: kv::Raw = item.value()?;
+                entries.push((key, value.to_vec()));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// In-memory [`SessionStore`], useful for tests and other ephemeral setups.
+#[derive(Default)]
+pub struct MemoryStore {
+    /// Backing map, guarded for interior mutability behind a shared reference.
+    entries: std::sync::Mutex<std::collections::BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    /// Create an empty [`MemoryStore`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, error::Error> {
+        Ok(self.entries.lock().expect("lock poisoned").get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<(), error::Error> {
+        self.entries
+            .lock()
+            .expect("lock poisoned")
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, error::Error> {
+        Ok(self
+            .entries
+            .lock()
+            .expect("lock poisoned")
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(store: &dyn SessionStore) {
+        assert_eq!(store.get("session:alice").unwrap(), None);
+
+        store.set("session:alice", b"token-a").unwrap();
+        store.set("session:bob", b"token-b").unwrap();
+        store.set("cache:other", b"unrelated").unwrap();
+
+        assert_eq!(
+            store.get("session:alice").unwrap(),
+            Some(b"token-a".to_vec())
+        );
+
+        let mut scanned = store.scan("session:").unwrap();
+        scanned.sort();
+        assert_eq!(
+            scanned,
+            vec![
+                ("session:alice".to_string(), b"token-a".to_vec()),
+                ("session:bob".to_string(), b"token-b".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn memory_store_behaves_like_disk_store() {
+        exercise(&MemoryStore::new());
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let disk = DiskStore::new(kv::Config::new(tmp_dir.path().join("store")))
+            .expect("failed to open disk store");
+        exercise(&disk);
+    }
+}
@@ -0,0 +1,314 @@
+//! Shamir secret sharing over `GF(257)`.
+
+use data_encoding::HEXLOWER;
+use rand::Rng as _;
+use thiserror::Error;
+
+/// Prime modulus each key byte is worked in. Strictly greater than any byte value (0..=255), so
+/// every byte has a unique representative in the field.
+const PRIME: u16 = 257;
+
+/// Errors that can occur while splitting or recovering a Shamir-shared secret.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The requested threshold/share-count combination is not splittable.
+    #[error("threshold k={k} must be non-zero and no greater than the share count n={n}")]
+    InvalidParams {
+        /// Requested threshold.
+        k: u8,
+        /// Requested number of shares.
+        n: u8,
+    },
+    /// Fewer than the threshold recorded on the shares themselves were supplied for recovery.
+    #[error("at least {required} distinct shares are required to recover this secret, got {got}")]
+    NotEnoughShares {
+        /// Threshold `k` recorded on the supplied shares.
+        required: u8,
+        /// Number of shares actually supplied.
+        got: usize,
+    },
+    /// Two or more supplied shares had the same x-coordinate.
+    #[error("shares must have distinct x-coordinates")]
+    DuplicateShare,
+    /// Supplied shares did not agree on the length of the secret.
+    #[error("shares disagree on the length of the secret")]
+    MismatchedShareLength,
+    /// Supplied shares did not agree on the threshold they were split with.
+    #[error("shares disagree on the threshold they were split with")]
+    MismatchedThreshold,
+    /// An encoded share (as produced by [`Share::to_encoded`]) could not be parsed.
+    #[error("share is not validly encoded")]
+    InvalidEncoding,
+}
+
+/// A single share of a split secret: a non-zero x-coordinate, the threshold `k` it was split
+/// with, and the corresponding y-coordinate for each byte of the secret.
+///
+/// Each y-coordinate is a field element modulo [`PRIME`] (`0..=256`), one past what a `u8` can
+/// hold, so they're kept as `u16` rather than truncating the `256` case to `0`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Share {
+    /// x-coordinate shared across every byte-polynomial of this share.
+    pub x: u8,
+    /// Threshold this share's secret was split with; [`recover`] needs at least this many shares.
+    pub k: u8,
+    /// Per-byte y-coordinates, each in `0..=256`.
+    pub ys: Vec<u16>,
+}
+
+impl Share {
+    /// Hex-encode this share so it can be written down or stored across devices.
+    #[must_use]
+    pub fn to_encoded(&self) -> String {
+        let ys_bytes: Vec<u8> = self.ys.iter().flat_map(|y| y.to_be_bytes()).collect();
+        format!(
+            "{:02x}-{:02x}-{}",
+            self.x,
+            self.k,
+            HEXLOWER.encode(&ys_bytes)
+        )
+    }
+
+    /// Parse a share previously produced by [`Share::to_encoded`].
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`Error::InvalidEncoding`] if `encoded` is not in the `x-k-ys` hex format
+    /// produced by [`Share::to_encoded`].
+    pub fn from_encoded(encoded: &str) -> Result<Self, Error> {
+        let mut parts = encoded.splitn(3, '-');
+        let x = parts.next().ok_or(Error::InvalidEncoding)?;
+        let k = parts.next().ok_or(Error::InvalidEncoding)?;
+        let ys = parts.next().ok_or(Error::InvalidEncoding)?;
+
+        let x = u8::from_str_radix(x, 16).map_err(|_| Error::InvalidEncoding)?;
+        let k = u8::from_str_radix(k, 16).map_err(|_| Error::InvalidEncoding)?;
+        let ys_bytes = HEXLOWER
+            .decode(ys.as_bytes())
+            .map_err(|_| Error::InvalidEncoding)?;
+        if ys_bytes.len() % 2 != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let ys = ys_bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        Ok(Self { x, k, ys })
+    }
+}
+
+/// Split `secret` into `n` [`Share`]s, any `k` of which reconstruct it.
+///
+/// # Errors
+///
+/// Errors if `k` is zero, `k` is greater than `n`, or `n` exceeds the 255 distinct non-zero
+/// x-coordinates a `u8` can hold.
+pub fn split(secret: &[u8], k: u8, n: u8) -> Result<Vec<Share>, Error> {
+    if k == 0 || k > n || usize::from(n) > 255 {
+        return Err(Error::InvalidParams { k, n });
+    }
+
+    let mut rng = rand::thread_rng();
+    // One random polynomial of degree k-1 per secret byte, constant term fixed to that byte.
+    let polynomials: Vec<Vec<u16>> = secret
+        .iter()
+        .map(|&byte| {
+            let mut coefficients = vec![u16::from(byte)];
+            for _ in 1..k {
+                coefficients.push(rng.gen_range(0..PRIME));
+            }
+            coefficients
+        })
+        .collect();
+
+    Ok((1..=n)
+        .map(|x| Share {
+            x,
+            k,
+            ys: polynomials
+                .iter()
+                .map(|coefficients| evaluate(coefficients, u16::from(x)))
+                .collect(),
+        })
+        .collect())
+}
+
+/// Reconstruct the original secret from at least `k` distinct [`Share`]s, where `k` is the
+/// threshold recorded on the shares themselves.
+///
+/// # Errors
+///
+/// Errors if shares disagree on the threshold they were split with, fewer than that threshold
+/// are given, shares disagree on the secret's length, or x-coordinates are not distinct.
+pub fn recover(shares: &[Share]) -> Result<Vec<u8>, Error> {
+    if shares.is_empty() {
+        return Err(Error::NotEnoughShares {
+            required: 1,
+            got: 0,
+        });
+    }
+
+    let k = shares[0].k;
+    if shares.iter().any(|share| share.k != k) {
+        return Err(Error::MismatchedThreshold);
+    }
+    if shares.len() < usize::from(k) {
+        return Err(Error::NotEnoughShares {
+            required: k,
+            got: shares.len(),
+        });
+    }
+
+    let len = shares[0].ys.len();
+    if shares.iter().any(|share| share.ys.len() != len) {
+        return Err(Error::MismatchedShareLength);
+    }
+
+    let mut xs = shares.iter().map(|share| share.x).collect::<Vec<_>>();
+    xs.sort_unstable();
+    if xs.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(Error::DuplicateShare);
+    }
+
+    Ok((0..len)
+        .map(|byte_index| {
+            let points: Vec<(u16, u16)> = shares
+                .iter()
+                .map(|share| (u16::from(share.x), share.ys[byte_index]))
+                .collect();
+            interpolate_at_zero(&points) as u8
+        })
+        .collect())
+}
+
+/// Evaluate a polynomial with the given coefficients (lowest degree first) at `x`, modulo
+/// [`PRIME`].
+fn evaluate(coefficients: &[u16], x: u16) -> u16 {
+    let (x, prime) = (u32::from(x), u32::from(PRIME));
+    coefficients.iter().rev().fold(0_u32, |acc, &coefficient| {
+        (acc * x + u32::from(coefficient)) % prime
+    }) as u16
+}
+
+/// Lagrange-interpolate the polynomial through `points` and evaluate it at `x = 0`, modulo
+/// [`PRIME`].
+fn interpolate_at_zero(points: &[(u16, u16)]) -> u16 {
+    let mut secret: i64 = 0;
+    for &(xi, yi) in points {
+        let mut numerator: i64 = 1;
+        let mut denominator: i64 = 1;
+        for &(xj, _) in points {
+            if xi != xj {
+                numerator = (numerator * i64::from(PRIME - xj)) % i64::from(PRIME);
+                let diff = (i64::from(xi) - i64::from(xj)).rem_euclid(i64::from(PRIME));
+                denominator = (denominator * diff) % i64::from(PRIME);
+            }
+        }
+        let term = i64::from(yi) * numerator % i64::from(PRIME)
+            * mod_inverse(denominator, i64::from(PRIME))
+            % i64::from(PRIME);
+        secret = (secret + term).rem_euclid(i64::from(PRIME));
+    }
+    secret as u16
+}
+
+/// Modular inverse of `a` modulo prime `p`, via Fermat's little theorem.
+fn mod_inverse(a: i64, p: i64) -> i64 {
+    mod_pow(a.rem_euclid(p), p - 2, p)
+}
+
+/// `base.pow(exp) % modulus`, without overflowing for the field sizes used here.
+fn mod_pow(mut base: i64, mut exp: i64, modulus: i64) -> i64 {
+    let mut result = 1;
+    base %= modulus;
+    while exp > 0 {
+        if exp % 2 == 1 {
+            result = result * base % modulus;
+        }
+        exp /= 2;
+        base = base * base % modulus;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_and_recovers() {
+        let secret = b"super secret signing key material".to_vec();
+        let shares = split(&secret, 3, 5).unwrap();
+
+        assert_eq!(recover(&shares[0..3]).unwrap(), secret);
+        assert_eq!(recover(&shares[1..4]).unwrap(), secret);
+        assert_eq!(recover(&shares).unwrap(), secret);
+    }
+
+    /// A y-coordinate of 256 is a legitimate field element mod 257, and used to get silently
+    /// truncated to 0 by storing `Share.ys` as `Vec<u8>`. Repeat the split/recover round trip
+    /// many times over a secret long enough to make hitting that value in at least one share
+    /// overwhelmingly likely, so a regression shows up as a flaky/failing assertion here rather
+    /// than shipping silently broken a quarter of the time.
+    #[test]
+    fn split_does_not_truncate_the_256_field_element() {
+        let secret: Vec<u8> = (0..=255).collect();
+        for _ in 0..200 {
+            let shares = split(&secret, 3, 5).unwrap();
+            assert_eq!(recover(&shares).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_threshold() {
+        assert!(matches!(
+            split(b"abc", 0, 5),
+            Err(Error::InvalidParams { .. })
+        ));
+        assert!(matches!(
+            split(b"abc", 6, 5),
+            Err(Error::InvalidParams { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_fewer_shares_than_the_threshold() {
+        let shares = split(b"super secret signing key material", 5, 10).unwrap();
+
+        assert!(matches!(
+            recover(&shares[0..2]),
+            Err(Error::NotEnoughShares {
+                required: 5,
+                got: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicate_shares_on_recovery() {
+        let shares = split(b"abc", 2, 3).unwrap();
+        let duplicated = vec![shares[0].clone(), shares[0].clone()];
+
+        assert!(matches!(recover(&duplicated), Err(Error::DuplicateShare)));
+    }
+
+    #[test]
+    fn encodes_and_decodes_shares() {
+        let shares = split(b"abc", 2, 3).unwrap();
+        let round_tripped = Share::from_encoded(&shares[0].to_encoded()).unwrap();
+
+        assert_eq!(round_tripped, shares[0]);
+    }
+
+    #[test]
+    fn rejects_malformed_encoding_distinctly_from_duplicate_shares() {
+        assert!(matches!(
+            Share::from_encoded("not-a-share"),
+            Err(Error::InvalidEncoding)
+        ));
+        assert!(matches!(
+            Share::from_encoded("01-02-zz"),
+            Err(Error::InvalidEncoding)
+        ));
+    }
+}